@@ -1,72 +1,71 @@
-use aoc;
-
 fn main() {
     let input = aoc::read_stdin().expect("cannot read stdin");
 
     let data = parse(&input);
     // println!("data: {data:#?}");
 
-    let result1: u32 = data.iter().sum();
-    println!("result: {result1}");
+    let result1 = part1(&data);
+    println!("part1: {result1}");
+
+    let result2 = part2(&data);
+    println!("part2: {result2}");
+}
+
+fn parse(input: &str) -> Vec<String> {
+    input.lines().map(|l| l.to_string()).collect()
 }
 
-fn parse(input: &str) -> Vec<u32> {
-    let number_words: Vec<(String, String)> = vec![
-        ("one".into(), "1".into()),
-        ("two".into(), "2".into()),
-        ("three".into(), "3".into()),
-        ("four".into(), "4".into()),
-        ("five".into(), "5".into()),
-        ("six".into(), "6".into()),
-        ("seven".into(), "7".into()),
-        ("eight".into(), "8".into()),
-        ("nine".into(), "9".into()),
-    ];
+// spelled out numbers, each worth its position + 1
+const NUMBER_WORDS: [&str; 9] = [
+    "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+];
 
-    enum NextPos {
-        First,
-        Last,
+// The number starting at byte i, either a digit or one of the (possibly empty) number words.
+// Every position is checked independently so overlapping words (e.g. "twone") are both found.
+fn number_at(s: &[u8], i: usize, number_words: &[&str]) -> Option<u32> {
+    if s[i].is_ascii_digit() {
+        return Some((s[i] - b'0') as u32);
     }
 
-    let next_number = |s: &str, pos: NextPos| -> u32 {
-        let mut vs: Vec<_> = number_words
-            .iter()
-            .flat_map(|(fs, ts)| {
-                s.match_indices(fs)
-                    .map(|(n, _)| (n, fs.len(), ts.to_owned()))
-            })
-            .collect();
+    number_words
+        .iter()
+        .position(|w| s[i..].starts_with(w.as_bytes()))
+        .map(|n| n as u32 + 1)
+}
 
-        vs.sort_by_key(|v| v.0);
+fn calibration_value(l: &str, number_words: &[&str]) -> u32 {
+    let s = l.as_bytes();
+    let nl = (0..s.len()).find_map(|i| number_at(s, i, number_words));
+    let nr = (0..s.len())
+        .rev()
+        .find_map(|i| number_at(s, i, number_words));
 
-        let mut s = s.to_string();
-        match pos {
-            NextPos::First => {
-                if let Some((n, l, ts)) = vs.first() {
-                    s.replace_range(*n..(*n + *l), &ts);
-                }
-            }
-            NextPos::Last => {
-                if let Some((n, l, ts)) = vs.last() {
-                    s.replace_range(*n..(*n + *l), &ts);
-                }
-            }
-        };
+    (nl.unwrap_or(0) * 10) + nr.unwrap_or(0)
+}
 
-        let ns: Vec<_> = s.chars().filter_map(|c| c.to_digit(10)).collect();
+#[test]
+fn test_calibration_value() {
+    assert_eq!(77, calibration_value("treb7uchet", &[]));
+    assert_eq!(0, calibration_value("eightwothree", &[]));
+    assert_eq!(83, calibration_value("eightwothree", &NUMBER_WORDS));
+    assert_eq!(21, calibration_value("twone", &NUMBER_WORDS));
+    assert_eq!(82, calibration_value("eightwo", &NUMBER_WORDS));
+    assert_eq!(18, calibration_value("oneight", &NUMBER_WORDS));
+    assert_eq!(79, calibration_value("sevenine", &NUMBER_WORDS));
+    assert_eq!(24, calibration_value("xtwone3four", &NUMBER_WORDS));
+    assert_eq!(76, calibration_value("7pqrstsixteen", &NUMBER_WORDS));
+    assert_eq!(11, calibration_value("one", &NUMBER_WORDS));
+    assert_eq!(0, calibration_value("", &NUMBER_WORDS));
+}
 
-        match pos {
-            NextPos::First => ns.first().map(|v| v.clone()).unwrap_or(0),
-            NextPos::Last => ns.last().map(|v| v.clone()).unwrap_or(0),
-        }
-    };
+// digits only
+fn part1(data: &[String]) -> u32 {
+    data.iter().map(|l| calibration_value(l, &[])).sum()
+}
 
-    input
-        .lines()
-        .map(|l| {
-            let nl = next_number(l, NextPos::First);
-            let nr = next_number(l, NextPos::Last);
-            (nl * 10) + nr
-        })
-        .collect()
+// digits and spelled out numbers
+fn part2(data: &[String]) -> u32 {
+    data.iter()
+        .map(|l| calibration_value(l, &NUMBER_WORDS))
+        .sum()
 }
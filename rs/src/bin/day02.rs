@@ -1,9 +1,7 @@
-use aoc;
 use nom::{
-    branch::alt,
     bytes::complete::tag,
-    character::complete::space1,
-    combinator::{all_consuming, map},
+    character::complete::{alpha1, space1},
+    combinator::all_consuming,
     multi::separated_list1,
     sequence::{preceded, separated_pair},
     IResult,
@@ -16,8 +14,34 @@ fn main() {
     let data = parse(&input).expect("cannot read input");
     // println!("{data:#?}");
 
-    let result1 = part1(&data);
+    let bag = parse_bag(&aoc::flag_value("--bag").unwrap_or(DEFAULT_BAG.to_string()))
+        .expect("cannot read bag");
+
+    let result1 = part1(&data, &bag);
     println!("part1: {result1}");
+
+    let result2 = part2(&data);
+    println!("part2: {result2}");
+}
+
+// The bag contents part1 checks each game against, in the same form as a Round.  Every color
+// must be listed since a Round treats a missing color as 0.
+const DEFAULT_BAG: &str = "12 red, 13 green, 14 blue";
+
+fn parse_bag(input: &str) -> Result<Round, String> {
+    match all_consuming(Round::parse)(input) {
+        Err(e) => Err(format!("{e}")),
+        Ok((_, v)) => {
+            if let Some(c) = ["red", "green", "blue"]
+                .iter()
+                .find(|c| !v.iter().any(|(_, vc)| vc == *c))
+            {
+                return Err(format!("bag: missing color {c:?}"));
+            }
+
+            Round::new(&v).map_err(|e| format!("bag: {e}"))
+        }
+    }
 }
 
 fn parse(input: &str) -> Result<Data, String> {
@@ -26,7 +50,7 @@ fn parse(input: &str) -> Result<Data, String> {
             .lines()
             .map(|l| match all_consuming(Game::parse)(l) {
                 Err(e) => Err(format!("{e}")),
-                Ok((_, v)) => Ok(v),
+                Ok((_, (index, rounds))) => Game::new(index, &rounds),
             })
             .collect::<Result<Vec<_>, _>>()?,
     ))
@@ -38,75 +62,109 @@ struct Data(Vec<Game>);
 #[derive(Debug)]
 struct Game(u32, Vec<Round>);
 
+type RawRound<'a> = Vec<(u32, &'a str)>;
+
 impl Game {
-    fn parse(i: &str) -> IResult<&str, Self> {
-        map(
-            separated_pair(
-                preceded(tag("Game "), nom::character::complete::u32),
-                tag(": "),
-                separated_list1(tag("; "), Round::parse),
-            ),
-            |(index, rounds)| Self(index, rounds),
+    fn new(index: u32, rounds: &[RawRound]) -> Result<Self, String> {
+        Ok(Self(
+            index,
+            rounds
+                .iter()
+                .enumerate()
+                .map(|(ri, r)| Round::new(r).map_err(|e| format!("game {index}: round {ri}: {e}")))
+                .collect::<Result<_, _>>()?,
+        ))
+    }
+
+    fn parse(i: &str) -> IResult<&str, (u32, Vec<RawRound<'_>>)> {
+        separated_pair(
+            preceded(tag("Game "), nom::character::complete::u32),
+            tag(": "),
+            separated_list1(tag("; "), Round::parse),
         )(i)
     }
+
+    // The fewest cubes of each color that make every round possible.
+    fn minimum_set(&self) -> Round {
+        self.1.iter().fold(Round::default(), |acc, round| Round {
+            red: u32::max(acc.red, round.red),
+            green: u32::max(acc.green, round.green),
+            blue: u32::max(acc.blue, round.blue),
+        })
+    }
 }
 
-#[derive(Debug)]
-struct Round(std::collections::HashMap<CubeColor, u32>);
+#[derive(Debug, Default)]
+struct Round {
+    red: u32,
+    green: u32,
+    blue: u32,
+}
 
 impl Round {
-    fn parse(i: &str) -> IResult<&str, Self> {
-        map(
-            separated_list1(
-                tag(", "),
-                separated_pair(nom::character::complete::u32, space1, CubeColor::parse),
-            ),
-            |vs| {
-                Self(
-                    vs.into_iter()
-                        .map(|(n, c)| (c, n))
-                        .collect::<std::collections::HashMap<_, _>>(),
-                )
-            },
+    fn new(cubes: &[(u32, &str)]) -> Result<Self, String> {
+        let mut rv = Self::default();
+        let mut seen = vec![];
+
+        for (n, c) in cubes {
+            let color = CubeColor::new(c).ok_or(format!("unknown color {c:?}"))?;
+            if seen.contains(&color) {
+                return Err(format!("duplicate color {c:?}"));
+            }
+            seen.push(color);
+
+            match color {
+                CubeColor::Red => rv.red = *n,
+                CubeColor::Green => rv.green = *n,
+                CubeColor::Blue => rv.blue = *n,
+            }
+        }
+
+        Ok(rv)
+    }
+
+    fn parse(i: &str) -> IResult<&str, RawRound<'_>> {
+        separated_list1(
+            tag(", "),
+            separated_pair(nom::character::complete::u32, space1, alpha1),
         )(i)
     }
+
+    fn is_possible(&self, bag: &Round) -> bool {
+        self.red <= bag.red && self.green <= bag.green && self.blue <= bag.blue
+    }
+
+    fn power(&self) -> u32 {
+        self.red * self.green * self.blue
+    }
 }
 
-#[derive(Debug, Hash, Eq, PartialEq)]
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
 enum CubeColor {
     Red,
     Green,
     Blue,
-    Other,
 }
 
 impl CubeColor {
-    fn parse(i: &str) -> IResult<&str, Self> {
-        map(alt((tag("red"), tag("green"), tag("blue"))), |v| match v {
-            "red" => Self::Red,
-            "green" => Self::Green,
-            "blue" => Self::Blue,
-            _ => Self::Other,
-        })(i)
+    fn new(s: &str) -> Option<Self> {
+        match s {
+            "red" => Some(Self::Red),
+            "green" => Some(Self::Green),
+            "blue" => Some(Self::Blue),
+            _ => None,
+        }
     }
 }
 
-fn part1(data: &Data) -> u32 {
+fn part1(data: &Data, bag: &Round) -> u32 {
     data.0
         .iter()
-        .filter_map(|Game(index, rounds)| {
-            if rounds.iter().all(|round| {
-                round.0.iter().all(|(c, n)| match c {
-                    CubeColor::Red => *n <= 12,
-                    CubeColor::Green => *n <= 13,
-                    CubeColor::Blue => *n <= 14,
-                    _ => true,
-                })
-            }) {
-                Some(*index)
-            } else {
-                None
-            }
-        })
+        .filter(|Game(_, rounds)| rounds.iter().all(|round| round.is_possible(bag)))
+        .map(|Game(index, _)| *index)
         .sum()
 }
+
+fn part2(data: &Data) -> u32 {
+    data.0.iter().map(|game| game.minimum_set().power()).sum()
+}
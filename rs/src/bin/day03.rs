@@ -1,4 +1,4 @@
-use aoc;
+use aoc::grid2d::Grid2D;
 
 fn main() {
     let input = aoc::read_stdin().expect("cannot read stdin");
@@ -9,144 +9,108 @@ fn main() {
 
     let result1 = part1(&data);
     println!("part1: {result1}");
+
+    let result2 = part2(&data);
+    println!("part2: {result2}");
 }
 
 fn parse(input: &str) -> Result<Data, String> {
-    Ok(Data(
-        input
+    let grid = Grid2D::new(
+        &input
             .lines()
-            .enumerate()
-            .map(|(ri, l)| {
-                let mut es = vec![];
-
-                let le = l.chars().enumerate().fold(None, |acc, (i, c)| {
-                    if c.is_digit(10) {
-                        match acc {
-                            Some(Element::Number {
-                                row,
-                                first_index,
-                                value,
-                                ..
-                            }) => Some(Element::Number {
-                                row,
-                                first_index,
-                                last_index: i,
-                                value: (value * 10) + c.to_digit(10).unwrap_or(0),
-                            }),
-                            _ => Some(Element::Number {
-                                row: ri,
-                                first_index: i,
-                                last_index: i,
-                                value: c.to_digit(10).unwrap_or(0),
-                            }),
-                        }
-                    } else if c == '.' {
-                        if acc.is_some() {
-                            es.push(acc.unwrap());
-                        }
-
-                        None
-                    } else {
-                        if acc.is_some() {
-                            es.push(acc.unwrap());
-                        }
+            .map(|l| l.chars().collect::<Vec<_>>())
+            .collect::<Vec<_>>(),
+    )?;
 
-                        es.push(Element::Symbol { index: i, value: c });
+    let mut numbers = vec![];
+    let mut number_index = Grid2D::filled(grid.get_width(), grid.get_height(), None);
+    for row in 0..grid.get_height() {
+        let mut current: Option<Number> = None;
 
-                        None
+        // one past the last column to close a number ending the row
+        for i in 0..=grid.get_width() {
+            match grid.get_yx(row, i).and_then(|c| c.to_digit(10)) {
+                Some(v) => {
+                    let n = current.get_or_insert(Number {
+                        row,
+                        first_index: i,
+                        last_index: i,
+                        value: 0,
+                    });
+                    n.last_index = i;
+                    n.value = (n.value * 10) + v;
+                    number_index.set_yx(row, i, Some(numbers.len()));
+                }
+                None => {
+                    if let Some(n) = current.take() {
+                        numbers.push(n);
                     }
-                });
-
-                if le.is_some() {
-                    es.push(le.unwrap());
                 }
+            }
+        }
+    }
 
-                es
-            })
-            .collect::<Vec<_>>(),
-    ))
+    Ok(Data {
+        grid,
+        numbers,
+        number_index,
+    })
 }
 
-#[derive(Debug, Hash, Eq, PartialEq)]
-struct Data(Vec<Vec<Element>>);
+#[derive(Debug)]
+struct Data {
+    grid: Grid2D<char>,
+    numbers: Vec<Number>,
+    // index into numbers of the Number covering each cell
+    number_index: Grid2D<Option<usize>>,
+}
+
+impl Data {
+    // Find all symbols as (row, index, value)
+    fn symbols(&self) -> impl Iterator<Item = (usize, usize, char)> + '_ {
+        self.grid
+            .iter()
+            .filter(|p| p.value != '.' && !p.value.is_ascii_digit())
+            .map(|p| (p.y, p.x, p.value))
+    }
+
+    // Find the (unique) numbers adjacent to the symbol at row i and column s_index
+    fn adjacent_numbers(&self, i: usize, s_index: usize) -> Vec<&Number> {
+        let mut ns = (i.saturating_sub(1)..=(i + 1))
+            .flat_map(|y| (s_index.saturating_sub(1)..=(s_index + 1)).map(move |x| (y, x)))
+            .filter_map(|(y, x)| self.number_index.get_yx(y, x).flatten())
+            .collect::<Vec<_>>();
+        ns.sort();
+        ns.dedup();
+
+        ns.into_iter().map(|n| &self.numbers[n]).collect()
+    }
+}
 
 #[derive(Debug, Hash, Eq, PartialEq)]
-enum Element {
-    Number {
-        row: usize,
-        first_index: usize,
-        last_index: usize,
-        value: u32,
-    },
-    Symbol {
-        index: usize,
-        value: char,
-    },
+struct Number {
+    row: usize,
+    first_index: usize,
+    last_index: usize,
+    value: u32,
 }
 
 fn part1(data: &Data) -> u32 {
-    data.0
-        .iter()
-        .enumerate()
-        .flat_map(|(i, es)| {
-            es.iter()
-                // Find all Element::Symbol in the current row
-                .filter(|e| match e {
-                    Element::Symbol { .. } => true,
-                    _ => false,
-                })
-                // Find all Symbol::Number adjacent to Element::Symbol
-                .flat_map(|se| {
-                    // We know "se" is an Element::Symbol but the compiler doesn't so we need to
-                    // pattern match to make the compiler happy and to extract its "index" (i.e.
-                    // column position).
-                    if let Element::Symbol { index: s_index, .. } = se {
-                        data.0
-                            .iter()
-                            .enumerate()
-                            // From the adjacent and current rows
-                            .filter(|(n, _)| *n >= i - 1 && *n <= i + 1)
-                            // Find all Element::Number items
-                            .flat_map(|(_, es)| {
-                                es.iter()
-                                    .filter(|e| {
-                                        if let Element::Number { .. } = e {
-                                            true
-                                        } else {
-                                            false
-                                        }
-                                    })
-                                    .collect::<Vec<_>>()
-                            })
-                            // Keep adjacent Symbol::Number items
-                            .filter(|ne| {
-                                if let Element::Number {
-                                    first_index: n_first_index,
-                                    last_index: n_last_index,
-                                    ..
-                                } = ne
-                                {
-                                    (*n_first_index <= *s_index && *n_last_index >= *s_index)
-                                        || (*n_first_index == *s_index + 1)
-                                        || (*n_last_index + 1 == *s_index)
-                                } else {
-                                    false
-                                }
-                            })
-                            .collect::<Vec<_>>()
-                    } else {
-                        vec![]
-                    }
-                })
-                .map(|e| e)
-                .collect::<Vec<_>>()
-        })
-        // We want a unique set of Element::Number
+    data.symbols()
+        .flat_map(|(i, s_index, _)| data.adjacent_numbers(i, s_index))
+        // We want a unique set of Number
         .collect::<std::collections::HashSet<_>>()
         .iter()
-        .map(|e| match e {
-            Element::Number { value, .. } => *value,
-            _ => 0,
-        })
+        .map(|n| n.value)
+        .sum()
+}
+
+fn part2(data: &Data) -> u32 {
+    data.symbols()
+        .filter(|(_, _, value)| *value == '*')
+        .map(|(i, s_index, _)| data.adjacent_numbers(i, s_index))
+        // A gear is a '*' adjacent to exactly two numbers
+        .filter(|ns| ns.len() == 2)
+        .map(|ns| ns.iter().map(|n| n.value).product::<u32>())
         .sum()
 }
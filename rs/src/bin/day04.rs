@@ -1,4 +1,3 @@
-use aoc;
 use nom::{
     bytes::complete::tag,
     character::complete::space1,
@@ -18,6 +17,9 @@ fn main() {
 
     let result1 = part1(&data);
     println!("part1: {result1}");
+
+    let result2 = part2(&data);
+    println!("part2: {result2}");
 }
 
 fn parse(input: &str) -> Result<Data, String> {
@@ -61,17 +63,19 @@ impl Card {
             },
         )(i)
     }
+
+    fn matches(&self) -> usize {
+        self.winning_numbers
+            .iter()
+            .filter(|n| self.chosen_numbers.contains(n))
+            .count()
+    }
 }
 
 fn part1(data: &Data) -> u32 {
     data.0
         .iter()
-        .map(|c| {
-            c.winning_numbers
-                .iter()
-                .filter(|n| c.chosen_numbers.contains(n))
-                .count()
-        })
+        .map(|c| c.matches())
         .map(|n| {
             if n > 0 {
                 u32::pow(2, (n as u32) - 1)
@@ -81,3 +85,20 @@ fn part1(data: &Data) -> u32 {
         })
         .sum()
 }
+
+fn part2(data: &Data) -> u32 {
+    // every card starts as a single original
+    let mut copies = vec![1; data.0.len()];
+
+    // each copy of a card wins one more copy of each of the next (matches) cards
+    for (i, c) in data.0.iter().enumerate() {
+        let n = copies[i];
+        copies
+            .iter_mut()
+            .skip(i + 1)
+            .take(c.matches())
+            .for_each(|v| *v += n);
+    }
+
+    copies.iter().sum()
+}
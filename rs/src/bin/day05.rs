@@ -1,11 +1,10 @@
-use aoc;
-use nom::branch::permutation;
 use nom::bytes::complete::tag;
 use nom::character::complete::line_ending;
-use nom::character::complete::{space0, space1};
+use nom::character::complete::{alpha1, space0, space1};
 use nom::combinator::all_consuming;
 use nom::combinator::map;
 use nom::multi::many0;
+use nom::multi::many1;
 use nom::multi::separated_list1;
 use nom::sequence::tuple;
 use nom::IResult;
@@ -19,36 +18,42 @@ fn main() {
 
     let result1 = part1(&data);
     println!("part1: {result1}");
+
+    let result2 = part2(&data).expect("cannot read seed ranges");
+    println!("part2: {result2}");
+
+    if let Some(v) = aoc::flag_value("--reverse") {
+        let locations = Interval::from_arg(&v).expect("cannot parse --reverse location");
+        data.reverse_lookup(locations)
+            .iter()
+            .for_each(|chain| println!("{chain}"));
+    }
 }
 
 fn parse(input: &str) -> Result<Data, String> {
     let result = all_consuming(map(
-        permutation((
-            seeds_parser,
-            map_parser("seed-to-soil"),
-            map_parser("soil-to-fertilizer"),
-            map_parser("fertilizer-to-water"),
-            map_parser("water-to-light"),
-            map_parser("light-to-temperature"),
-            map_parser("temperature-to-humidity"),
-            map_parser("humidity-to-location"),
-        )),
-        |(v1, v2, v3, v4, v5, v6, v7, v8)| Data {
-            seeds: v1,
-            seed_to_soil_map: v2,
-            soil_to_fertilizer_map: v3,
-            fertilizer_to_water_map: v4,
-            water_to_light_map: v5,
-            light_to_temperature_map: v6,
-            temperature_to_humidity_map: v7,
-            humidity_to_location_map: v8,
-        },
+        tuple((seeds_parser, many1(RangeMap::parse))),
+        |(seeds, maps)| Data { seeds, maps },
     ))(input);
 
-    match result {
-        Err(e) => Err(format!("{e}")),
-        Ok((_, v)) => Ok(v),
+    let data = match result {
+        Err(e) => return Err(format!("{e}")),
+        Ok((_, v)) => v,
+    };
+
+    // the maps must chain from seed to location
+    let mut from = "seed";
+    for m in data.maps.iter() {
+        if m.from != from {
+            return Err(format!("{}-to-{} map does not follow {from}", m.from, m.to));
+        }
+        from = &m.to;
+    }
+    if from != "location" {
+        return Err(format!("maps end at {from} instead of location"));
     }
+
+    Ok(data)
 }
 
 fn seeds_parser(i: &str) -> IResult<&str, Vec<u64>> {
@@ -63,81 +68,325 @@ fn seeds_parser(i: &str) -> IResult<&str, Vec<u64>> {
     )(i)
 }
 
-fn map_parser<'a>(label: &'a str) -> impl FnMut(&'a str) -> IResult<&'a str, Vec<(u64, u64, u64)>> {
-    map(
-        tuple((
-            tuple((tag(label), space1, tag("map:"), space0, line_ending)),
-            separated_list1(
-                line_ending,
-                tuple((
-                    nom::character::complete::u64,
-                    space1,
-                    nom::character::complete::u64,
-                    space1,
-                    nom::character::complete::u64,
-                )),
-            ),
-            many0(line_ending),
-        )),
-        |(_, vs, _)| {
-            let mut vs = vs
-                .into_iter()
-                .map(|(dst_start, _, src_start, _, range)| (src_start, dst_start, range))
-                .collect::<Vec<_>>();
-            vs.sort_by_key(|v| v.0);
-            vs
-        },
-    )
-}
-
 #[derive(Debug)]
 struct Data {
     seeds: Vec<u64>,
-    seed_to_soil_map: Vec<(u64, u64, u64)>,
-    soil_to_fertilizer_map: Vec<(u64, u64, u64)>,
-    fertilizer_to_water_map: Vec<(u64, u64, u64)>,
-    water_to_light_map: Vec<(u64, u64, u64)>,
-    light_to_temperature_map: Vec<(u64, u64, u64)>,
-    temperature_to_humidity_map: Vec<(u64, u64, u64)>,
-    humidity_to_location_map: Vec<(u64, u64, u64)>,
+    maps: Vec<RangeMap>,
 }
 
 impl Data {
-    fn seed_to_location(&self, seed: u64) -> u64 {
-        let v1 = translate(&self.seed_to_soil_map, seed);
-        let v2 = translate(&self.soil_to_fertilizer_map, v1);
-        let v3 = translate(&self.fertilizer_to_water_map, v2);
-        let v4 = translate(&self.water_to_light_map, v3);
-        let v5 = translate(&self.light_to_temperature_map, v4);
-        let v6 = translate(&self.temperature_to_humidity_map, v5);
-        translate(&self.humidity_to_location_map, v6)
+    fn seed_to_location_map(&self) -> RangeMap {
+        self.maps
+            .iter()
+            .skip(1)
+            .fold(self.maps[0].clone(), |acc, m| acc.compose(m).unwrap())
+    }
+
+    // Walk the maps backwards from the locations to every seed that produces one of them.
+    fn reverse_lookup(&self, locations: Interval) -> Vec<Chain> {
+        let seeds = self.maps.iter().rev().fold(vec![locations], |acc, m| {
+            let inverse = m.inverse();
+            acc.into_iter()
+                .flat_map(|v| inverse.translate_interval(v))
+                .collect()
+        });
+
+        // each seed piece was produced by a single piece of every map, so going forwards again
+        // gives exactly the values that lead from it to the locations
+        seeds
+            .into_iter()
+            .map(|seed| {
+                let mut chain = vec![(self.maps[0].from.clone(), seed)];
+                for m in self.maps.iter() {
+                    let v = m.translate_interval(chain.last().unwrap().1)[0];
+                    chain.push((m.to.clone(), v));
+                }
+
+                Chain(chain)
+            })
+            .collect()
     }
 }
 
-fn translate(map: &[(u64, u64, u64)], i: u64) -> u64 {
-    let v = map
-        .iter()
-        .fold(None, |acc, (src_start, dst_start, range)| match acc {
-            Some(v) => Some(v),
+// The values of every category from seed to location.
+#[derive(Debug)]
+struct Chain(Vec<(String, Interval)>);
+
+impl std::fmt::Display for Chain {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            self.0
+                .iter()
+                .map(|(category, v)| format!("{category} {v}"))
+                .collect::<Vec<_>>()
+                .join(" -> ")
+        )
+    }
+}
+
+// Half-open range of values [start, end)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Interval {
+    start: u64,
+    end: u64,
+}
+
+impl Interval {
+    fn new(start: u64, end: u64) -> Self {
+        Self { start, end }
+    }
+
+    // "N" for a single value or "N..M"
+    fn from_arg(s: &str) -> Result<Self, String> {
+        let parse = |v: &str| v.parse::<u64>().map_err(|e| format!("{v:?}: {e}"));
+
+        let (start, end) = match s.split_once("..") {
+            Some((start, end)) => (parse(start)?, parse(end)?),
             None => {
-                if i >= *src_start && i < (*src_start + *range) {
-                    Some(*dst_start + (i - *src_start))
-                } else {
-                    None
+                let v = parse(s)?;
+                let end = v.checked_add(1).ok_or(format!("{v} is too large"))?;
+                (v, end)
+            }
+        };
+
+        if start >= end {
+            return Err(format!("{s:?} is an empty range"));
+        }
+
+        Ok(Self::new(start, end))
+    }
+}
+
+impl std::fmt::Display for Interval {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.start.checked_add(1) == Some(self.end) {
+            write!(f, "{}", self.start)
+        } else {
+            write!(f, "{}..{}", self.start, self.end)
+        }
+    }
+}
+
+// Translation from one category to the next as (src_start, dst_start, range) entries sorted by
+// src_start.  Values not covered by any entry map to themselves.
+#[derive(Debug, Clone)]
+struct RangeMap {
+    from: String,
+    to: String,
+    entries: Vec<(u64, u64, u64)>,
+}
+
+impl RangeMap {
+    fn parse(i: &str) -> IResult<&str, Self> {
+        map(
+            tuple((
+                Self::parse_header,
+                separated_list1(
+                    line_ending,
+                    tuple((
+                        nom::character::complete::u64,
+                        space1,
+                        nom::character::complete::u64,
+                        space1,
+                        nom::character::complete::u64,
+                    )),
+                ),
+                many0(line_ending),
+            )),
+            |((from, to), vs, _)| {
+                let mut entries = vs
+                    .into_iter()
+                    .map(|(dst_start, _, src_start, _, range)| (src_start, dst_start, range))
+                    .collect::<Vec<_>>();
+                entries.sort_by_key(|v| v.0);
+
+                Self {
+                    from: from.to_string(),
+                    to: to.to_string(),
+                    entries,
                 }
+            },
+        )(i)
+    }
+
+    // "X-to-Y map:"
+    fn parse_header(i: &str) -> IResult<&str, (&str, &str)> {
+        map(
+            tuple((
+                alpha1,
+                tag("-to-"),
+                alpha1,
+                space1,
+                tag("map:"),
+                space0,
+                line_ending,
+            )),
+            |(from, _, to, _, _, _, _)| (from, to),
+        )(i)
+    }
+
+    fn get(&self, v: u64) -> u64 {
+        // the last entry starting at or before v is the only one that can contain it
+        let i = self
+            .entries
+            .partition_point(|(src_start, _, _)| *src_start <= v);
+        match i.checked_sub(1).map(|i| self.entries[i]) {
+            Some((src_start, dst_start, range)) if v < src_start + range => {
+                dst_start + (v - src_start)
             }
-        });
+            _ => v,
+        }
+    }
+
+    // Split the interval on the entries it overlaps, translating each overlapped piece and
+    // passing the gaps between entries through unchanged.
+    fn translate_interval(&self, interval: Interval) -> Vec<Interval> {
+        let mut rv = vec![];
+        let mut current = interval.start;
+
+        for (src_start, dst_start, range) in self.entries.iter() {
+            let src_end = *src_start + *range;
+            if src_end <= current {
+                continue;
+            }
+            if *src_start >= interval.end {
+                break;
+            }
+
+            if current < *src_start {
+                rv.push(Interval::new(current, *src_start));
+                current = *src_start;
+            }
+
+            let end = u64::min(src_end, interval.end);
+            rv.push(Interval::new(
+                *dst_start + (current - *src_start),
+                *dst_start + (end - *src_start),
+            ));
+            current = end;
+        }
+
+        if current < interval.end {
+            rv.push(Interval::new(current, interval.end));
+        }
 
-    match v {
-        Some(v) => v,
-        None => i,
+        rv
+    }
+
+    // Every (src_start, dst_start, range) piece of the map including the identity gaps around
+    // the entries.
+    fn pieces(&self) -> Vec<(u64, u64, u64)> {
+        let mut rv = vec![];
+        let mut current = 0;
+
+        for (src_start, dst_start, range) in self.entries.iter() {
+            if current < *src_start {
+                rv.push((current, current, *src_start - current));
+            }
+            rv.push((*src_start, *dst_start, *range));
+            current = *src_start + *range;
+        }
+
+        if current < u64::MAX {
+            rv.push((current, current, u64::MAX - current));
+        }
+
+        rv
+    }
+
+    fn inverse(&self) -> InverseRangeMap {
+        let mut entries = self
+            .pieces()
+            .into_iter()
+            .map(|(src_start, dst_start, range)| (dst_start, src_start, range))
+            .collect::<Vec<_>>();
+        entries.sort_by_key(|v| v.0);
+
+        InverseRangeMap { entries }
+    }
+
+    // A single map equivalent to applying self and then other.
+    fn compose(&self, other: &Self) -> Result<Self, String> {
+        if self.to != other.from {
+            return Err(format!(
+                "cannot compose {}-to-{} with {}-to-{}",
+                self.from, self.to, other.from, other.to
+            ));
+        }
+
+        // translate the whole domain through self, keeping track of where each resulting piece
+        // came from, then through other
+        let mut entries = vec![];
+        let mut src_start = 0;
+        for piece in self.translate_interval(Interval::new(0, u64::MAX)) {
+            for v in other.translate_interval(piece) {
+                let range = v.end - v.start;
+                if v.start != src_start {
+                    entries.push((src_start, v.start, range));
+                }
+                src_start += range;
+            }
+        }
+
+        Ok(Self {
+            from: self.from.clone(),
+            to: other.to.clone(),
+            entries,
+        })
+    }
+}
+
+// Inverse of a RangeMap as (src_start, dst_start, range) entries covering every value.  An entry
+// and an identity gap of the original map can produce the same value so entries may overlap, and
+// a value has one result per entry containing it.
+#[derive(Debug)]
+struct InverseRangeMap {
+    entries: Vec<(u64, u64, u64)>,
+}
+
+impl InverseRangeMap {
+    fn translate_interval(&self, interval: Interval) -> Vec<Interval> {
+        self.entries
+            .iter()
+            .filter_map(|(src_start, dst_start, range)| {
+                let start = u64::max(*src_start, interval.start);
+                let end = u64::min(*src_start + *range, interval.end);
+
+                (start < end).then(|| {
+                    Interval::new(
+                        *dst_start + (start - *src_start),
+                        *dst_start + (end - *src_start),
+                    )
+                })
+            })
+            .collect()
     }
 }
 
 fn part1(data: &Data) -> u64 {
+    let map = data.seed_to_location_map();
+
     data.seeds
         .iter()
-        .map(|seed| data.seed_to_location(*seed))
+        .map(|seed| map.get(*seed))
         .min()
         .unwrap_or(0)
 }
+
+fn part2(data: &Data) -> Result<u64, String> {
+    let map = data.seed_to_location_map();
+
+    // the seeds are (start, length) pairs
+    let seeds = data.seeds.chunks_exact(2);
+    if let [v] = seeds.remainder() {
+        return Err(format!("seed {v} has no range length"));
+    }
+
+    Ok(seeds
+        .flat_map(|v| map.translate_interval(Interval::new(v[0], v[0] + v[1])))
+        .map(|v| v.start)
+        .min()
+        .unwrap_or(0))
+}
@@ -1,8 +1,7 @@
-use aoc;
 use nom::branch::permutation;
 use nom::bytes::complete::tag;
 use nom::character::complete::line_ending;
-use nom::character::complete::space1;
+use nom::character::complete::{space0, space1};
 use nom::combinator::all_consuming;
 use nom::combinator::map;
 use nom::multi::many0;
@@ -20,19 +19,18 @@ fn main() {
 
     let result1 = part1(&data);
     println!("part1: {result1}");
+
+    let data = parse_kerned(&input).expect("cannot parse kerned input");
+    // println!("{data:#?}");
+
+    let result2 = part2(&data).expect("cannot find the kerned race");
+    println!("part2: {result2}");
 }
 
 fn parse(input: &str) -> Result<Data, String> {
     let result = all_consuming(map(
         permutation((time_parser, distance_parser)),
-        |(times, distances)| {
-            Data(
-                times
-                    .into_iter()
-                    .zip(distances.into_iter())
-                    .collect::<Vec<_>>(),
-            )
-        },
+        |(times, distances)| Data(times.into_iter().zip(distances).collect::<Vec<_>>()),
     ))(input);
 
     match result {
@@ -41,11 +39,16 @@ fn parse(input: &str) -> Result<Data, String> {
     }
 }
 
+// The spaces between the digits are just bad kerning so there is really only one race.
+fn parse_kerned(input: &str) -> Result<Data, String> {
+    parse(&input.replace(' ', ""))
+}
+
 fn time_parser(i: &str) -> IResult<&str, Vec<u64>> {
     map(
         tuple((
             preceded(
-                tuple((tag("Time:"), space1)),
+                tuple((tag("Time:"), space0)),
                 separated_list1(space1, nom::character::complete::u64),
             ),
             many0(line_ending),
@@ -58,7 +61,7 @@ fn distance_parser(i: &str) -> IResult<&str, Vec<u64>> {
     map(
         tuple((
             preceded(
-                tuple((tag("Distance:"), space1)),
+                tuple((tag("Distance:"), space0)),
                 separated_list1(space1, nom::character::complete::u64),
             ),
             many0(line_ending),
@@ -70,14 +73,52 @@ fn distance_parser(i: &str) -> IResult<&str, Vec<u64>> {
 #[derive(Debug)]
 struct Data(Vec<(u64, u64)>);
 
+// The number of hold times h in 0..=t that beat the record d, i.e. h * (t - h) > d.  The
+// winners lie strictly between the roots of h^2 - t*h + d = 0, so start from the integer square
+// root estimate of the lower root and step to the exact first winner, which also takes care of
+// the root itself being an integer (that hold time only ties the record).
+fn ways_to_win(t: u64, d: u64) -> u64 {
+    let (t, d) = (t as u128, d as u128);
+    let wins = |h: u128| h * (t - h) > d;
+
+    if t * t < 4 * d {
+        return 0;
+    }
+
+    let mut lo = (t - (t * t - 4 * d).isqrt()) / 2;
+    while lo <= t / 2 && !wins(lo) {
+        lo += 1;
+    }
+    while lo > 0 && wins(lo - 1) {
+        lo -= 1;
+    }
+
+    if lo > t / 2 {
+        0
+    } else {
+        // the winners are symmetric around t / 2
+        (t - 2 * lo + 1) as u64
+    }
+}
+
+#[test]
+fn test_ways_to_win() {
+    assert_eq!(4, ways_to_win(7, 9));
+    assert_eq!(8, ways_to_win(15, 40));
+    // the roots 10 and 20 are integers and only tie the record
+    assert_eq!(9, ways_to_win(30, 200));
+    assert_eq!(0, ways_to_win(4, 4));
+    assert_eq!(0, ways_to_win(3, 10));
+    assert_eq!(71503, ways_to_win(71530, 940200));
+}
+
 fn part1(data: &Data) -> u64 {
-    data.0
-        .iter()
-        .map(|(t, d)| {
-            (0..=*t)
-                .into_iter()
-                .filter(|th| *th * (*t - *th) > *d)
-                .count() as u64
-        })
-        .product()
+    data.0.iter().map(|(t, d)| ways_to_win(*t, *d)).product()
+}
+
+fn part2(data: &Data) -> Result<u64, String> {
+    match data.0.as_slice() {
+        [(t, d)] => Ok(ways_to_win(*t, *d)),
+        races => Err(format!("expected a single race but found {}", races.len())),
+    }
 }
@@ -1,4 +1,3 @@
-use aoc;
 use nom::character::complete::one_of;
 use nom::character::complete::space1;
 use nom::combinator::all_consuming;
@@ -17,6 +16,9 @@ fn main() {
 
     let result1 = part1(&data);
     println!("part1: {result1}");
+
+    let result2 = part2(&data);
+    println!("part2: {result2}");
 }
 
 fn parse(input: &str) -> Result<Data, String> {
@@ -35,28 +37,42 @@ fn parse(input: &str) -> Result<Data, String> {
 #[derive(Debug)]
 struct Data(Vec<Hand>);
 
+// Part 1 plays the standard rules, part 2 treats J as a joker: the weakest card on its own but a
+// wildcard toward the hand type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Rules {
+    Standard,
+    Jokers,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 struct Hand {
     cards: Vec<Card>,
     bet: u64,
     hand_type: HandType,
+    rules: Rules,
 }
 
 impl Hand {
-    fn new(cards: Vec<Card>, bet: u64) -> Self {
-        let hand_type = HandType::new(&cards);
+    fn new(cards: Vec<Card>, bet: u64, rules: Rules) -> Self {
+        let hand_type = HandType::new(&cards, rules);
 
         Self {
             cards,
             bet,
             hand_type,
+            rules,
         }
     }
 
+    fn with_rules(&self, rules: Rules) -> Self {
+        Self::new(self.cards.clone(), self.bet, rules)
+    }
+
     fn parse(i: &str) -> IResult<&str, Self> {
         all_consuming(map(
             separated_pair(count(Card::parse, 5), space1, nom::character::complete::u64),
-            |(cards, bet)| Hand::new(cards, bet),
+            |(cards, bet)| Hand::new(cards, bet, Rules::Standard),
         ))(i)
     }
 }
@@ -69,7 +85,7 @@ impl Ord for Hand {
                 .iter()
                 .zip(other.cards.iter())
                 .fold(Ordering::Equal, |acc, (s, o)| match acc {
-                    Ordering::Equal => s.cmp(o),
+                    Ordering::Equal => s.rank(self.rules).cmp(&o.rank(self.rules)),
                     _ => acc,
                 })
         } else {
@@ -96,75 +112,34 @@ enum HandType {
 }
 
 impl HandType {
-    fn new(cards: &[Card]) -> Self {
-        let mut ordered_cards = cards.to_vec().clone();
-        ordered_cards.sort();
+    fn new(cards: &[Card], rules: Rules) -> Self {
+        let is_wild = |c: &Card| rules == Rules::Jokers && *c == Card::J;
 
-        let mut hand_types: Vec<HandType> = vec![];
-        let last = ordered_cards
+        // sizes of the groups of equal cards, largest first
+        let mut counts = [0; 13];
+        cards
             .iter()
-            .fold((None, Card::N2), |acc, c| match acc {
-                (None, _) => (Some(HandType::HighCard), c.clone()),
-                (Some(HandType::HighCard), cp) => {
-                    if *c == cp {
-                        (Some(HandType::OnePair), c.clone())
-                    } else {
-                        hand_types.push(HandType::HighCard);
-                        (Some(HandType::HighCard), c.clone())
-                    }
-                }
-                (Some(HandType::OnePair), cp) => {
-                    if *c == cp {
-                        (Some(HandType::ThreeOfAKind), c.clone())
-                    } else {
-                        hand_types.push(HandType::OnePair);
-                        (Some(HandType::HighCard), c.clone())
-                    }
-                }
-                (Some(HandType::ThreeOfAKind), cp) => {
-                    if *c == cp {
-                        (Some(HandType::FourOfAKind), c.clone())
-                    } else {
-                        hand_types.push(HandType::ThreeOfAKind);
-                        (Some(HandType::HighCard), c.clone())
-                    }
-                }
-                (Some(HandType::FourOfAKind), cp) => {
-                    if *c == cp {
-                        (Some(HandType::FiveOfAKind), c.clone())
-                    } else {
-                        hand_types.push(HandType::FourOfAKind);
-                        (Some(HandType::HighCard), c.clone())
-                    }
-                }
-                _ => acc,
-            });
-        hand_types.push(last.0.unwrap());
+            .filter(|c| !is_wild(c))
+            .for_each(|c| counts[*c as usize] += 1);
+        let mut counts = counts.into_iter().filter(|n| *n > 0).collect::<Vec<_>>();
+        counts.sort_by(|a, b| b.cmp(a));
 
-        hand_types.sort_by(|a, b| b.cmp(a));
+        // wildcards always make the best hand by joining the largest group
+        let n_wild = cards.iter().filter(|c| is_wild(c)).count();
+        match counts.first_mut() {
+            Some(n) => *n += n_wild,
+            None => counts.push(n_wild),
+        }
 
-        hand_types
-            .into_iter()
-            .reduce(|acc, v| match acc {
-                HandType::FiveOfAKind => acc,
-                HandType::FourOfAKind => acc,
-                HandType::ThreeOfAKind => {
-                    if let HandType::OnePair = v {
-                        HandType::FullHouse
-                    } else {
-                        acc
-                    }
-                }
-                HandType::OnePair => {
-                    if let HandType::OnePair = v {
-                        HandType::TwoPair
-                    } else {
-                        acc
-                    }
-                }
-                _ => acc,
-            })
-            .unwrap()
+        match counts.as_slice() {
+            [5] => HandType::FiveOfAKind,
+            [4, ..] => HandType::FourOfAKind,
+            [3, 2] => HandType::FullHouse,
+            [3, ..] => HandType::ThreeOfAKind,
+            [2, 2, ..] => HandType::TwoPair,
+            [2, ..] => HandType::OnePair,
+            _ => HandType::HighCard,
+        }
     }
 }
 
@@ -173,45 +148,147 @@ fn test_hand_type_new() {
     // 32T3K 765
     assert_eq!(
         HandType::OnePair,
-        HandType::new(vec![Card::N3, Card::N2, Card::T, Card::N3, Card::K].as_ref())
+        HandType::new(
+            vec![Card::N3, Card::N2, Card::T, Card::N3, Card::K].as_ref(),
+            Rules::Standard
+        )
     );
     // KK677 28
     assert_eq!(
         HandType::TwoPair,
-        HandType::new(vec![Card::K, Card::K, Card::N6, Card::N7, Card::N7].as_ref())
+        HandType::new(
+            vec![Card::K, Card::K, Card::N6, Card::N7, Card::N7].as_ref(),
+            Rules::Standard
+        )
     );
     // KTJJT 220
     assert_eq!(
         HandType::TwoPair,
-        HandType::new(vec![Card::K, Card::T, Card::J, Card::J, Card::T].as_ref())
+        HandType::new(
+            vec![Card::K, Card::T, Card::J, Card::J, Card::T].as_ref(),
+            Rules::Standard
+        )
     );
     // T55J5 684
     assert_eq!(
         HandType::ThreeOfAKind,
-        HandType::new(vec![Card::T, Card::N5, Card::N5, Card::J, Card::N5].as_ref())
+        HandType::new(
+            vec![Card::T, Card::N5, Card::N5, Card::J, Card::N5].as_ref(),
+            Rules::Standard
+        )
     );
     // QQQJA 483
     assert_eq!(
         HandType::ThreeOfAKind,
-        HandType::new(vec![Card::Q, Card::Q, Card::Q, Card::J, Card::A].as_ref())
+        HandType::new(
+            vec![Card::Q, Card::Q, Card::Q, Card::J, Card::A].as_ref(),
+            Rules::Standard
+        )
     );
     // 87887 -
     assert_eq!(
         HandType::FullHouse,
-        HandType::new(vec![Card::N8, Card::N7, Card::N8, Card::N8, Card::N7].as_ref())
+        HandType::new(
+            vec![Card::N8, Card::N7, Card::N8, Card::N8, Card::N7].as_ref(),
+            Rules::Standard
+        )
     );
     // 87888 -
     assert_eq!(
         HandType::FourOfAKind,
-        HandType::new(vec![Card::N8, Card::N7, Card::N8, Card::N8, Card::N8].as_ref())
+        HandType::new(
+            vec![Card::N8, Card::N7, Card::N8, Card::N8, Card::N8].as_ref(),
+            Rules::Standard
+        )
     );
     // 77777 -
     assert_eq!(
         HandType::FiveOfAKind,
-        HandType::new(vec![Card::N7, Card::N7, Card::N7, Card::N7, Card::N7].as_ref())
+        HandType::new(
+            vec![Card::N7, Card::N7, Card::N7, Card::N7, Card::N7].as_ref(),
+            Rules::Standard
+        )
+    );
+    // 32T3K 765
+    assert_eq!(
+        HandType::OnePair,
+        HandType::new(
+            vec![Card::N3, Card::N2, Card::T, Card::N3, Card::K].as_ref(),
+            Rules::Jokers
+        )
+    );
+    // KTJJT 220
+    assert_eq!(
+        HandType::FourOfAKind,
+        HandType::new(
+            vec![Card::K, Card::T, Card::J, Card::J, Card::T].as_ref(),
+            Rules::Jokers
+        )
+    );
+    // T55J5 684
+    assert_eq!(
+        HandType::FourOfAKind,
+        HandType::new(
+            vec![Card::T, Card::N5, Card::N5, Card::J, Card::N5].as_ref(),
+            Rules::Jokers
+        )
+    );
+    // QJJQ2 -
+    assert_eq!(
+        HandType::FourOfAKind,
+        HandType::new(
+            vec![Card::Q, Card::J, Card::J, Card::Q, Card::N2].as_ref(),
+            Rules::Jokers
+        )
+    );
+    // 2345J -
+    assert_eq!(
+        HandType::OnePair,
+        HandType::new(
+            vec![Card::N2, Card::N3, Card::N4, Card::N5, Card::J].as_ref(),
+            Rules::Jokers
+        )
+    );
+    // 2233J -
+    assert_eq!(
+        HandType::FullHouse,
+        HandType::new(
+            vec![Card::N2, Card::N2, Card::N3, Card::N3, Card::J].as_ref(),
+            Rules::Jokers
+        )
+    );
+    // JJJJJ -
+    assert_eq!(
+        HandType::FiveOfAKind,
+        HandType::new(
+            vec![Card::J, Card::J, Card::J, Card::J, Card::J].as_ref(),
+            Rules::Jokers
+        )
     );
 }
 
+#[test]
+fn test_hand_type_new_permutation_invariant() {
+    // every sequence of 5 cards drawn from 5 distinct cards (including J) covers every hand type
+    // and every permutation of each hand, so comparing each to its sorted form checks them all
+    let alphabet = [Card::N2, Card::N3, Card::T, Card::J, Card::A];
+    for n in 0..alphabet.len().pow(5) {
+        let cards = (0..5)
+            .map(|i| alphabet[(n / alphabet.len().pow(i)) % alphabet.len()])
+            .collect::<Vec<_>>();
+        let mut sorted = cards.clone();
+        sorted.sort();
+
+        for rules in [Rules::Standard, Rules::Jokers] {
+            assert_eq!(
+                HandType::new(&sorted, rules),
+                HandType::new(&cards, rules),
+                "{cards:?} {rules:?}"
+            );
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
 enum Card {
     N2,
@@ -252,10 +329,22 @@ impl Card {
     fn parse(i: &str) -> IResult<&str, Self> {
         map(one_of("23456789TJQKA"), |c| Card::new(c).unwrap())(i)
     }
+
+    // Strength of the card when breaking ties between hands of the same type.
+    fn rank(&self, rules: Rules) -> u8 {
+        match (rules, self) {
+            (Rules::Jokers, Card::J) => 0,
+            _ => *self as u8 + 1,
+        }
+    }
 }
 
-fn part1(data: &Data) -> u64 {
-    let mut hands = data.0.clone();
+fn total_winnings(data: &Data, rules: Rules) -> u64 {
+    let mut hands = data
+        .0
+        .iter()
+        .map(|h| h.with_rules(rules))
+        .collect::<Vec<_>>();
     hands.sort();
 
     hands
@@ -264,3 +353,11 @@ fn part1(data: &Data) -> u64 {
         .map(|(i, hand)| (i as u64 + 1) * hand.bet)
         .sum()
 }
+
+fn part1(data: &Data) -> u64 {
+    total_winnings(data, Rules::Standard)
+}
+
+fn part2(data: &Data) -> u64 {
+    total_winnings(data, Rules::Jokers)
+}
@@ -5,16 +5,31 @@ fn main() {
     let data = parse(&input).expect("cannot parse input");
     // println!("{data:#?}");
 
+    if aoc::has_flag("--empty") {
+        println!("empty columns: {:?}", data.empty_columns());
+        println!("empty rows: {:?}", data.empty_rows());
+    }
+
     let result1 = part1(&data);
     println!("part1: {result1}");
 
     let result2 = part2(&data);
     println!("part2: {result2}");
+
+    if let Some(factor) = aoc::flag_value("--factor") {
+        let factor = factor
+            .parse::<usize>()
+            .ok()
+            .filter(|v| *v > 0)
+            .expect("--factor must be a positive integer");
+        let result = data.expanded_pair_distance_sum(factor);
+        println!("factor {factor}: {result}");
+    }
 }
 
 fn parse(input: &str) -> Result<Data, String> {
-    Ok(Data(
-        input
+    Ok(Data {
+        galaxies: input
             .lines()
             .enumerate()
             .flat_map(move |(yi, l)| {
@@ -24,109 +39,74 @@ fn parse(input: &str) -> Result<Data, String> {
                 })
             })
             .collect::<Vec<_>>(),
-    ))
+        width: input.lines().map(|l| l.len()).max().unwrap_or(0),
+        height: input.lines().count(),
+    })
 }
 
 #[derive(Debug, Clone)]
-struct Data(Vec<Point>);
+struct Data {
+    galaxies: Vec<Point>,
+    // dimensions of the (unexpanded) universe, including any trailing empty rows/columns
+    width: usize,
+    height: usize,
+}
 
 impl Data {
-    fn expand(&self, factor: usize) -> Self {
-        self.expand_horizontal(factor).expand_vertical(factor)
+    fn empty_columns(&self) -> Vec<usize> {
+        (0..self.width)
+            .filter(|xi| !self.galaxies.iter().any(|Point(x, _)| x == xi))
+            .collect()
     }
 
-    fn expand_horizontal(&self, factor: usize) -> Self {
-        let max_x = self.0.iter().map(|Point(x, _)| *x).max().unwrap();
-
-        // find empty columns (largest to smallest)
-        let empty: Vec<_> = (0..max_x)
-            .rev()
-            .filter(|xi| !self.0.iter().any(|Point(x, _)| *x == *xi))
-            .collect();
-
-        // fold over empty columns shifting right each entry located right of the current column
-        empty.into_iter().fold(self.clone(), |acc, xi| {
-            Self(
-                acc.0
-                    .iter()
-                    .map(|Point(x, y)| {
-                        if *x > xi {
-                            Point(*x + (factor - 1), *y)
-                        } else {
-                            Point(*x, *y)
-                        }
-                    })
-                    .collect::<Vec<_>>(),
-            )
-        })
+    fn empty_rows(&self) -> Vec<usize> {
+        (0..self.height)
+            .filter(|yi| !self.galaxies.iter().any(|Point(_, y)| y == yi))
+            .collect()
     }
 
-    fn expand_vertical(&self, factor: usize) -> Self {
-        let max_y = self.0.iter().map(|Point(_, y)| *y).max().unwrap();
-
-        // find empty rows (largest to smallest)
-        let empty: Vec<_> = (0..max_y)
-            .rev()
-            .filter(|yi| !self.0.iter().any(|Point(_, y)| *y == *yi))
-            .collect();
-
-        // fold over empty rows shifting down each entry located below the current row
-        empty.into_iter().fold(self.clone(), |acc, yi| {
-            Self(
-                acc.0
-                    .iter()
-                    .map(|Point(x, y)| {
-                        if *y > yi {
-                            Point(*x, *y + (factor - 1))
-                        } else {
-                            Point(*x, *y)
-                        }
-                    })
-                    .collect::<Vec<_>>(),
-            )
-        })
+    // Empty universes and single galaxies have no pairs and so sum to 0.
+    fn expanded_pair_distance_sum(&self, factor: usize) -> usize {
+        let xs = self.galaxies.iter().map(|Point(x, _)| *x).collect();
+        let ys = self.galaxies.iter().map(|Point(_, y)| *y).collect();
+
+        expanded_axis_distance_sum(xs, factor) + expanded_axis_distance_sum(ys, factor)
     }
 }
 
 #[derive(Debug, Clone)]
 struct Point(usize, usize);
 
-impl Point {
-    fn distance(&self, other: &Self) -> usize {
-        self.0.abs_diff(other.0) + self.1.abs_diff(other.1)
+// Sum of the distances between every pair of coordinates along one axis once each empty line
+// (one without any coordinate on it) has been expanded to `factor` lines.  With the coordinates
+// sorted, each one is further along than all previous ones, so its contribution is its expanded
+// coordinate times the number of previous coordinates less their (prefix) sum.
+fn expanded_axis_distance_sum(mut coords: Vec<usize>, factor: usize) -> usize {
+    coords.sort_unstable();
+
+    let mut sum = 0;
+    let mut prefix_sum = 0;
+    let mut n_occupied = 0;
+    let mut last = None;
+    for (i, c) in coords.into_iter().enumerate() {
+        // count the distinct occupied lines before this one
+        if last.is_some_and(|v| v != c) {
+            n_occupied += 1;
+        }
+        last = Some(c);
+
+        let expanded = c + (factor - 1) * (c - n_occupied);
+        sum += expanded * i - prefix_sum;
+        prefix_sum += expanded;
     }
+
+    sum
 }
 
 fn part1(data: &Data) -> usize {
-    let data = data.expand(2);
-
-    // find all pairs of points
-    let pairs = (0..(data.0.len() - 1))
-        .flat_map(|i1| ((i1 + 1)..data.0.len()).map(move |i2| (i1, i2)))
-        .map(|(i1, i2)| {
-            (
-                data.0.get(i1).unwrap().clone(),
-                data.0.get(i2).unwrap().clone(),
-            )
-        })
-        .collect::<Vec<_>>();
-
-    pairs.into_iter().map(|(p1, p2)| p1.distance(&p2)).sum()
+    data.expanded_pair_distance_sum(2)
 }
 
 fn part2(data: &Data) -> usize {
-    let data = data.expand(1_000_000);
-
-    // find all pairs of points
-    let pairs = (0..(data.0.len() - 1))
-        .flat_map(|i1| ((i1 + 1)..data.0.len()).map(move |i2| (i1, i2)))
-        .map(|(i1, i2)| {
-            (
-                data.0.get(i1).unwrap().clone(),
-                data.0.get(i2).unwrap().clone(),
-            )
-        })
-        .collect::<Vec<_>>();
-
-    pairs.into_iter().map(|(p1, p2)| p1.distance(&p2)).sum()
+    data.expanded_pair_distance_sum(1_000_000)
 }
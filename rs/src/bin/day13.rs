@@ -15,75 +15,154 @@ fn main() {
     let data = parse(&input).expect("cannot parse input");
     // println!("{data:#?}");
 
+    if aoc::has_flag("--report") {
+        report(&data);
+    }
+
     let result1 = part1(&data);
     println!("part1: {result1}");
+
+    let result2 = part2(&data);
+    println!("part2: {result2}");
 }
 
 fn parse(input: &str) -> Result<Data, String> {
-    let result = all_consuming(Data::parse)(input);
+    let result = all_consuming(separated_list1(
+        tuple((line_ending, line_ending)),
+        Pattern::parse,
+    ))(input);
 
     match result {
         Err(e) => Err(format!("{e}")),
-        Ok((_, v)) => Ok(v),
+        Ok((_, v)) => Ok(Data(
+            v.iter()
+                .enumerate()
+                .map(|(i, rows)| Pattern::new(rows).map_err(|e| format!("pattern {i}: {e}")))
+                .collect::<Result<_, _>>()?,
+        )),
     }
 }
 
 #[derive(Debug)]
 struct Data(Vec<Pattern>);
 
-impl Data {
-    fn parse(i: &str) -> IResult<&str, Self> {
-        map(
-            separated_list1(tuple((line_ending, line_ending)), Pattern::parse),
-            Self,
-        )(i)
-    }
-}
-
+// Each row and column is encoded as a bitmask (bit n set = Rocks at offset n) so comparing two
+// lines is a single integer compare and the number of differing cells is a popcount.
 #[derive(Debug)]
-struct Pattern(Vec<Row>);
+struct Pattern {
+    rows: Vec<u64>,
+    cols: Vec<u64>,
+}
 
 impl Pattern {
-    fn parse(i: &str) -> IResult<&str, Self> {
-        map(separated_list1(line_ending, Row::parse), Self)(i)
+    fn new(rows: &[Row]) -> Result<Self, String> {
+        let height = rows.len();
+        let width = rows.first().map(|r| r.0.len()).unwrap_or(0);
+
+        if width > 64 || height > 64 {
+            return Err(format!(
+                "pattern is {width}x{height} which exceeds the maximum of 64x64"
+            ));
+        }
+
+        let mut rv = Self {
+            rows: vec![0; height],
+            cols: vec![0; width],
+        };
+
+        for (ri, r) in rows.iter().enumerate() {
+            if r.0.len() != width {
+                return Err(format!(
+                    "row {} has length {} which does not match the previous length {}",
+                    ri,
+                    r.0.len(),
+                    width
+                ));
+            }
+
+            for (ci, v) in r.0.iter().enumerate() {
+                if let Symbol::Rocks = v {
+                    rv.rows[ri] |= 1 << ci;
+                    rv.cols[ci] |= 1 << ri;
+                }
+            }
+        }
+
+        Ok(rv)
     }
 
-    fn reflection_point(&self) -> Option<usize> {
-        self.0
-            .windows(2)
-            .enumerate()
-            .filter(|(_, w)| w[0] == w[1])
-            .filter(|(i, _)| {
-                (1..=usize::min(*i, self.0.len() - (i + 2)))
-                    .all(|o| self.0[i - o] == self.0[i + 1 + o])
-            })
-            .map(|(i, _)| i)
-            .collect::<Vec<_>>()
-            .first()
-            .copied()
+    fn parse(i: &str) -> IResult<&str, Vec<Row>> {
+        separated_list1(line_ending, Row::parse)(i)
     }
 
-    fn transpose(&self) -> Self {
-        let height = self.0.len();
-        let width = if height > 0 {
-            self.0.first().unwrap().0.len()
-        } else {
-            0
-        };
+    // Find the line (after line i) whose mirrored lines differ in exactly `defects` cells.
+    fn reflection_point(lines: &[u64], defects: u32) -> Option<usize> {
+        (0..lines.len().saturating_sub(1)).find(|i| Self::defects(lines, *i) == defects)
+    }
 
-        Self(
-            (0..width)
-                .map(|ci| {
-                    Row((0..height)
-                        .map(|ri| self.0.get(ri).unwrap().0.get(ci).unwrap().clone())
-                        .collect::<Vec<_>>())
+    // Count the cells that differ when mirroring across the line after line i.
+    fn defects(lines: &[u64], i: usize) -> u32 {
+        (0..usize::min(i + 1, lines.len() - (i + 1)))
+            .map(|o| (lines[i - o] ^ lines[i + 1 + o]).count_ones())
+            .sum()
+    }
+
+    fn mirror_lines(&self, max_defects: u32) -> Vec<MirrorLine> {
+        let candidates = |axis: Axis, lines: &[u64]| {
+            (0..lines.len().saturating_sub(1))
+                .map(|i| MirrorLine {
+                    axis,
+                    after: i + 1,
+                    smudges: Self::defects(lines, i),
                 })
-                .collect::<Vec<_>>(),
-        )
+                .filter(|m| m.smudges <= max_defects)
+                .collect::<Vec<_>>()
+        };
+
+        let mut rv = candidates(Axis::Horizontal, &self.rows);
+        rv.extend(candidates(Axis::Vertical, &self.cols));
+
+        rv
     }
+
+    fn summary(&self, defects: u32) -> usize {
+        if let Some(h) = Self::reflection_point(&self.rows, defects) {
+            return 100 * (h + 1);
+        }
+
+        if let Some(v) = Self::reflection_point(&self.cols, defects) {
+            return v + 1;
+        }
+
+        0
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Axis {
+    Horizontal,
+    Vertical,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug)]
+struct MirrorLine {
+    axis: Axis,
+    // number of rows (Horizontal) or columns (Vertical) before the line
+    after: usize,
+    smudges: u32,
+}
+
+impl std::fmt::Display for MirrorLine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.axis {
+            Axis::Horizontal => write!(f, "horizontal after row {}", self.after)?,
+            Axis::Vertical => write!(f, "vertical after column {}", self.after)?,
+        }
+        write!(f, " ({} smudges)", self.smudges)
+    }
+}
+
+#[derive(Debug)]
 struct Row(Vec<Symbol>);
 
 impl Row {
@@ -116,19 +195,29 @@ impl std::fmt::Display for Symbol {
     }
 }
 
-fn part1(data: &Data) -> usize {
-    data.0
-        .iter()
-        .map(|p| {
-            if let Some(h) = p.reflection_point() {
-                return 100 * (h + 1);
-            }
+// List every mirror line with at most one smudge and flag the patterns that do not have exactly
+// one answer for part 1 (0 smudges) or part 2 (1 smudge).
+fn report(data: &Data) {
+    for (i, p) in data.0.iter().enumerate() {
+        let lines = p.mirror_lines(1);
+
+        println!("pattern {i}: {}x{}", p.cols.len(), p.rows.len());
+        lines.iter().for_each(|m| println!("  {m}"));
 
-            if let Some(v) = p.transpose().reflection_point() {
-                return v + 1;
+        for (part, smudges) in [("part1", 0), ("part2", 1)] {
+            match lines.iter().filter(|m| m.smudges == smudges).count() {
+                0 => println!("  WARNING: {part}: no mirror line with {smudges} smudges"),
+                1 => {}
+                n => println!("  WARNING: {part}: {n} mirror lines with {smudges} smudges"),
             }
+        }
+    }
+}
+
+fn part1(data: &Data) -> usize {
+    data.0.iter().map(|p| p.summary(0)).sum()
+}
 
-            0
-        })
-        .sum()
+fn part2(data: &Data) -> usize {
+    data.0.iter().map(|p| p.summary(1)).sum()
 }
@@ -6,6 +6,7 @@ use nom::combinator::map;
 use nom::multi::many1;
 use nom::multi::separated_list1;
 use nom::IResult;
+use std::collections::HashMap;
 
 fn main() {
     let input = aoc::read_stdin().expect("cannot read stdin");
@@ -17,8 +18,8 @@ fn main() {
     let result1 = part1(&data);
     println!("part1: {result1}");
 
-    // let result2 = part2(&data);
-    // println!("part2: {result2}");
+    let result2 = part2(&data);
+    println!("part2: {result2}");
 }
 
 fn parse(input: &str) -> Result<Data, String> {
@@ -76,10 +77,9 @@ impl Data {
                         let n_round = v.iter().filter(|v| matches!(v, RockType::Round)).count();
 
                         // produce new slice of (round * round_count)
-                        std::iter::repeat(RockType::Round)
-                            .take(n_round)
+                        std::iter::repeat_n(RockType::Round, n_round)
                             // produce new slice of (empty * (len - round_count))
-                            .chain(std::iter::repeat(RockType::Empty).take(v.len() - n_round))
+                            .chain(std::iter::repeat_n(RockType::Empty, v.len() - n_round))
                             .collect::<Vec<_>>()
                     })
                     .collect::<Vec<_>>()
@@ -205,6 +205,100 @@ impl std::fmt::Display for RockType {
     }
 }
 
+// Packed bitset of the round rock positions (bit index = y * width + x).  Cube rocks never
+// move so they live in a separate BoardMask and only the round rocks take part in Hash/Eq.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct PackedData(Vec<u64>);
+
+impl PackedData {
+    fn new(width: usize, height: usize) -> Self {
+        Self(vec![0; (width * height).div_ceil(64)])
+    }
+
+    fn get(&self, i: usize) -> bool {
+        self.0[i / 64] & (1 << (i % 64)) != 0
+    }
+
+    fn set(&mut self, i: usize) {
+        self.0[i / 64] |= 1 << (i % 64);
+    }
+}
+
+impl std::convert::From<&Data> for PackedData {
+    fn from(item: &Data) -> Self {
+        let height = (item.0).len();
+        let width = (item.0).first().map(|r| (r.0).len()).unwrap_or(0);
+        let mut rv = Self::new(width, height);
+
+        for (ri, r) in (item.0).iter().enumerate() {
+            for (ci, v) in (r.0).iter().enumerate() {
+                if let RockType::Round = v {
+                    rv.set(ri * width + ci);
+                }
+            }
+        }
+
+        rv
+    }
+}
+
+// The static part of a board: its dimensions and the cube rock positions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct BoardMask {
+    width: usize,
+    height: usize,
+    cubes: PackedData,
+}
+
+impl BoardMask {
+    fn index(&self, y: usize, x: usize) -> usize {
+        y * self.width + x
+    }
+
+    fn unpack(&self, board: &PackedData) -> Data {
+        (0..self.height)
+            .map(|ri| {
+                (0..self.width)
+                    .map(|ci| {
+                        let i = self.index(ri, ci);
+                        if self.cubes.get(i) {
+                            RockType::Cube
+                        } else if board.get(i) {
+                            RockType::Round
+                        } else {
+                            RockType::Empty
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .into() // Row
+            })
+            .collect::<Vec<_>>()
+            .into() // Data
+    }
+}
+
+impl std::convert::From<&Data> for BoardMask {
+    fn from(item: &Data) -> Self {
+        let height = (item.0).len();
+        let width = (item.0).first().map(|r| (r.0).len()).unwrap_or(0);
+        let mut cubes = PackedData::new(width, height);
+
+        for (ri, r) in (item.0).iter().enumerate() {
+            for (ci, v) in (r.0).iter().enumerate() {
+                if let RockType::Cube = v {
+                    cubes.set(ri * width + ci);
+                }
+            }
+        }
+
+        Self {
+            width,
+            height,
+            cubes,
+        }
+    }
+}
+
 #[derive(Debug)]
 enum Direction {
     North,
@@ -218,10 +312,27 @@ fn part1(data: &Data) -> usize {
 }
 
 fn part2(data: &Data) -> usize {
+    const N_SPINS: usize = 1_000_000_000;
+
+    let mask = BoardMask::from(data);
+    let mut history: HashMap<PackedData, usize> = HashMap::new();
+    let mut boards: Vec<PackedData> = vec![];
+
     let mut current = data.clone();
-    // for _ in 0..1_000_000_000 {
-    //     current = current.spin();
-    // }
+    for n in 0..N_SPINS {
+        let packed = PackedData::from(&current);
+        if let Some(start) = history.get(&packed) {
+            // the spins from start..n repeat forever
+            let cycle_len = n - start;
+            let i = start + (N_SPINS - start) % cycle_len;
+
+            return mask.unpack(&boards[i]).north_beam_load();
+        }
+
+        history.insert(packed.clone(), n);
+        boards.push(packed);
+        current = current.spin();
+    }
 
     current.north_beam_load()
 }
@@ -1,3 +1,13 @@
+use nom::branch::alt;
+use nom::bytes::complete::tag;
+use nom::character::complete::alpha1;
+use nom::character::complete::one_of;
+use nom::combinator::all_consuming;
+use nom::combinator::map;
+use nom::sequence::preceded;
+use nom::sequence::tuple;
+use nom::IResult;
+
 fn main() {
     let input = aoc::read_stdin().expect("cannot read stdin");
 
@@ -6,18 +16,154 @@ fn main() {
 
     let result1 = part1(&data);
     println!("part1: {result1}");
-}
 
-fn parse(input: &str) -> Result<Vec<String>, String> {
-    Ok(input.split(',').map(|v| v.to_string()).collect::<Vec<_>>())
+    let result2 = part2(&data);
+    println!("part2: {result2}");
 }
 
-fn part1(data: &[String]) -> u32 {
-    data.iter()
-        .map(|v| {
-            v.chars()
-                .map(|v| v as u8)
-                .fold(0, |a, v| ((a + v as u32) * 17) % 256)
+fn parse(input: &str) -> Result<Vec<Step>, String> {
+    input
+        .trim_end()
+        .split(',')
+        .enumerate()
+        .map(|(i, v)| match all_consuming(Step::parse)(v) {
+            Err(e) => Err(format!("step {i} ({v:?}): {e}")),
+            Ok((_, v)) => Ok(v),
         })
-        .sum()
+        .collect::<Result<_, _>>()
+}
+
+#[derive(Debug)]
+struct Step {
+    label: String,
+    op: Op,
+}
+
+impl Step {
+    fn parse(i: &str) -> IResult<&str, Self> {
+        map(tuple((alpha1, Op::parse)), |(label, op)| Self {
+            label: label.to_string(),
+            op,
+        })(i)
+    }
+}
+
+impl std::fmt::Display for Step {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.op {
+            Op::Remove => write!(f, "{}-", self.label),
+            Op::Insert(v) => write!(f, "{}={}", self.label, v),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Op {
+    Remove,
+    Insert(u8),
+}
+
+impl Op {
+    fn parse(i: &str) -> IResult<&str, Self> {
+        alt((
+            map(tag("-"), |_| Self::Remove),
+            // focal lengths are a single digit 1 through 9
+            map(preceded(tag("="), one_of("123456789")), |c| {
+                Self::Insert(c.to_digit(10).unwrap() as u8)
+            }),
+        ))(i)
+    }
+}
+
+fn hash(v: &str) -> u8 {
+    v.chars()
+        .map(|v| v as u8)
+        .fold(0, |a, v| ((a + v as u32) * 17) % 256) as u8
+}
+
+#[derive(Debug)]
+struct Lens {
+    label: String,
+    focal_length: u8,
+}
+
+#[derive(Debug)]
+struct Boxes(Vec<Vec<Lens>>);
+
+impl Boxes {
+    fn new() -> Self {
+        Self((0..256).map(|_| vec![]).collect())
+    }
+
+    fn insert(&mut self, label: &str, focal_length: u8) {
+        let lenses = &mut self.0[hash(label) as usize];
+
+        match lenses.iter_mut().find(|l| l.label == label) {
+            Some(l) => l.focal_length = focal_length,
+            None => lenses.push(Lens {
+                label: label.to_string(),
+                focal_length,
+            }),
+        }
+    }
+
+    fn remove(&mut self, label: &str) {
+        self.0[hash(label) as usize].retain(|l| l.label != label);
+    }
+
+    fn focusing_power(&self) -> usize {
+        self.0
+            .iter()
+            .enumerate()
+            .flat_map(|(bi, lenses)| {
+                lenses
+                    .iter()
+                    .enumerate()
+                    .map(move |(li, l)| (bi + 1) * (li + 1) * l.focal_length as usize)
+            })
+            .sum()
+    }
+}
+
+impl std::fmt::Display for Boxes {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            self.0
+                .iter()
+                .enumerate()
+                .filter(|(_, lenses)| !lenses.is_empty())
+                .map(|(bi, lenses)| {
+                    format!(
+                        "Box {bi}: {}",
+                        lenses
+                            .iter()
+                            .map(|l| format!("[{} {}]", l.label, l.focal_length))
+                            .collect::<Vec<_>>()
+                            .join(" ")
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        )
+    }
+}
+
+fn part1(data: &[Step]) -> u32 {
+    data.iter().map(|v| hash(&v.to_string()) as u32).sum()
+}
+
+fn part2(data: &[Step]) -> usize {
+    let mut boxes = Boxes::new();
+
+    for step in data.iter() {
+        match step.op {
+            Op::Remove => boxes.remove(&step.label),
+            Op::Insert(v) => boxes.insert(&step.label, v),
+        }
+        // println!("After \"{step}\":\n{boxes}\n");
+    }
+
+    boxes.focusing_power()
 }
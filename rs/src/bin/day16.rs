@@ -7,6 +7,10 @@ use nom::combinator::map;
 use nom::multi::many1;
 use nom::multi::separated_list1;
 use nom::IResult;
+use std::collections::HashMap;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::time::Instant;
 
 fn main() {
     let input = aoc::read_stdin().expect("cannot read stdin");
@@ -20,6 +24,23 @@ fn main() {
 
     let result2 = part2(&data);
     println!("part2: {result2}");
+
+    // --render [x,y,dir] defaults to the part1 beam
+    if aoc::has_flag("--render") {
+        let starting_beam = match aoc::flag_value("--render").filter(|v| !v.starts_with("--")) {
+            Some(v) => Beam::from_arg(&data, &v).expect("cannot parse --render beam"),
+            None => Beam::new(Point::new(0, 0), Direction::East),
+        };
+        println!("{}", render(&data, &energize(&data, &starting_beam)));
+    }
+
+    if aoc::has_flag("--heatmap") {
+        println!("{}", heatmap(&data));
+    }
+
+    if aoc::has_flag("--bench") {
+        bench_part2(&data);
+    }
 }
 
 fn parse(input: &str) -> Result<Data, String> {
@@ -87,7 +108,7 @@ impl std::fmt::Display for Tile {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 enum Direction {
     North,
     East,
@@ -95,6 +116,12 @@ enum Direction {
     West,
 }
 
+impl Direction {
+    fn bit(&self) -> u8 {
+        1 << (*self as u8)
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
 struct Point {
     x: usize,
@@ -139,186 +166,95 @@ impl Beam {
         Self { point, dir }
     }
 
-    fn next(&self, tile: &Tile) -> Vec<Self> {
-        match (&self.dir, tile) {
-            (Direction::North, Tile::AngleLeft) => {
-                let mut next = vec![];
-                if let Some(point) = self.point.next(Direction::West) {
-                    next.push(Self::new(point, Direction::West));
-                }
-                next
-            }
-            (Direction::North, Tile::AngleRight) => {
-                let mut next = vec![];
-                if let Some(point) = self.point.next(Direction::East) {
-                    next.push(Self::new(point, Direction::East));
-                }
-                next
-            }
-            (Direction::North, Tile::Vertical) => {
-                let mut next = vec![];
-                if let Some(point) = self.point.next(Direction::North) {
-                    next.push(Self::new(point, Direction::North));
-                }
-                next
-            }
-            (Direction::North, Tile::Horizontal) => {
-                let mut next = vec![];
-                if let Some(point) = self.point.next(Direction::West) {
-                    next.push(Self::new(point, Direction::West));
-                }
-                if let Some(point) = self.point.next(Direction::East) {
-                    next.push(Self::new(point, Direction::East));
-                }
-                next
-            }
-            (Direction::North, Tile::Space) => {
-                let mut next = vec![];
-                if let Some(point) = self.point.next(Direction::North) {
-                    next.push(Self::new(point, Direction::North));
-                }
-                next
-            }
-            (Direction::East, Tile::AngleLeft) => {
-                let mut next = vec![];
-                if let Some(point) = self.point.next(Direction::South) {
-                    next.push(Self::new(point, Direction::South));
-                }
-                next
-            }
-            (Direction::East, Tile::AngleRight) => {
-                let mut next = vec![];
-                if let Some(point) = self.point.next(Direction::North) {
-                    next.push(Self::new(point, Direction::North));
-                }
-                next
-            }
-            (Direction::East, Tile::Vertical) => {
-                let mut next = vec![];
-                if let Some(point) = self.point.next(Direction::North) {
-                    next.push(Self::new(point, Direction::North));
-                }
-                if let Some(point) = self.point.next(Direction::South) {
-                    next.push(Self::new(point, Direction::South));
-                }
-                next
-            }
-            (Direction::East, Tile::Horizontal) => {
-                let mut next = vec![];
-                if let Some(point) = self.point.next(Direction::East) {
-                    next.push(Self::new(point, Direction::East));
-                }
-                next
-            }
-            (Direction::East, Tile::Space) => {
-                let mut next = vec![];
-                if let Some(point) = self.point.next(Direction::East) {
-                    next.push(Self::new(point, Direction::East));
-                }
-                next
-            }
-            (Direction::South, Tile::AngleLeft) => {
-                let mut next = vec![];
-                if let Some(point) = self.point.next(Direction::East) {
-                    next.push(Self::new(point, Direction::East));
-                }
-                next
-            }
-            (Direction::South, Tile::AngleRight) => {
-                let mut next = vec![];
-                if let Some(point) = self.point.next(Direction::West) {
-                    next.push(Self::new(point, Direction::West));
-                }
-                next
-            }
-            (Direction::South, Tile::Vertical) => {
-                let mut next = vec![];
-                if let Some(point) = self.point.next(Direction::South) {
-                    next.push(Self::new(point, Direction::South));
-                }
-                next
-            }
-            (Direction::South, Tile::Horizontal) => {
-                let mut next = vec![];
-                if let Some(point) = self.point.next(Direction::West) {
-                    next.push(Self::new(point, Direction::West));
-                }
-                if let Some(point) = self.point.next(Direction::East) {
-                    next.push(Self::new(point, Direction::East));
-                }
-                next
-            }
-            (Direction::South, Tile::Space) => {
-                let mut next = vec![];
-                if let Some(point) = self.point.next(Direction::South) {
-                    next.push(Self::new(point, Direction::South));
-                }
-                next
-            }
-            (Direction::West, Tile::AngleLeft) => {
-                let mut next = vec![];
-                if let Some(point) = self.point.next(Direction::North) {
-                    next.push(Self::new(point, Direction::North));
-                }
-                next
-            }
-            (Direction::West, Tile::AngleRight) => {
-                let mut next = vec![];
-                if let Some(point) = self.point.next(Direction::South) {
-                    next.push(Self::new(point, Direction::South));
-                }
-                next
-            }
-            (Direction::West, Tile::Vertical) => {
-                let mut next = vec![];
-                if let Some(point) = self.point.next(Direction::North) {
-                    next.push(Self::new(point, Direction::North));
-                }
-                if let Some(point) = self.point.next(Direction::South) {
-                    next.push(Self::new(point, Direction::South));
-                }
-                next
-            }
-            (Direction::West, Tile::Horizontal) => {
-                let mut next = vec![];
-                if let Some(point) = self.point.next(Direction::West) {
-                    next.push(Self::new(point, Direction::West));
-                }
-                next
-            }
-            (Direction::West, Tile::Space) => {
-                let mut next = vec![];
-                if let Some(point) = self.point.next(Direction::West) {
-                    next.push(Self::new(point, Direction::West));
-                }
-                next
-            }
+    // "x,y,dir" where dir is one of N, E, S or W
+    fn from_arg(data: &Data, s: &str) -> Result<Self, String> {
+        let [x, y, dir] = s.split(',').collect::<Vec<_>>()[..] else {
+            return Err(format!("{s:?} is not x,y,dir"));
+        };
+
+        let parse = |v: &str| v.parse::<usize>().map_err(|e| format!("{v:?}: {e}"));
+        let point = Point::new(parse(x)?, parse(y)?);
+        if data.0.get_yx(point.y, point.x).is_none() {
+            return Err(format!("{x},{y} is outside the contraption"));
         }
+
+        let dir = match dir {
+            "N" => Direction::North,
+            "E" => Direction::East,
+            "S" => Direction::South,
+            "W" => Direction::West,
+            _ => return Err(format!("{dir:?} is not one of N, E, S or W")),
+        };
+
+        Ok(Self::new(point, dir))
+    }
+
+    fn next<'a>(&'a self, tile: &Tile) -> impl Iterator<Item = Self> + 'a {
+        BEAM_EXITS[*tile as usize][self.dir as usize]
+            .iter()
+            .filter_map(|dir| self.point.next(*dir).map(|point| Self::new(point, *dir)))
     }
 }
 
-fn simulate(data: &Data, starting_beam: &Beam) -> usize {
+// The directions a beam leaves a tile in, indexed by [Tile][Direction of travel].
+const BEAM_EXITS: [[&[Direction]; 4]; 5] = {
+    use Direction::*;
+    [
+        // Space
+        [&[North], &[East], &[South], &[West]],
+        // AngleLeft (\)
+        [&[West], &[South], &[East], &[North]],
+        // AngleRight (/)
+        [&[East], &[North], &[West], &[South]],
+        // Vertical
+        [&[North], &[North, South], &[South], &[North, South]],
+        // Horizontal
+        [&[West, East], &[East], &[West, East], &[West]],
+    ]
+};
+
+// Returns the bitflags of the directions each tile has been entered in.
+fn energize(data: &Data, starting_beam: &Beam) -> Grid2D<u8> {
     let mut beams = std::collections::VecDeque::from([starting_beam.clone()]);
-    let mut beam_history = std::collections::HashSet::<Beam>::new();
+    let mut beam_history = Grid2D::filled(data.0.get_width(), data.0.get_height(), 0_u8);
 
-    while !beams.is_empty() {
-        // println!("beams: {beams:?}");
-        let beam = beams.pop_front().unwrap();
-        beam_history.insert(beam.clone());
+    while let Some(beam) = beams.pop_front() {
+        // println!("beam: {beam:?}");
+        let seen = beam_history.get_yx(beam.point.y, beam.point.x).unwrap();
+        if seen & beam.dir.bit() != 0 {
+            continue;
+        }
+        beam_history.set_yx(beam.point.y, beam.point.x, seen | beam.dir.bit());
 
         let tile = data.0.get_yx(beam.point.y, beam.point.x).unwrap();
         beam.next(&tile)
-            .into_iter()
             .filter(|b| data.0.get_yx(b.point.y, b.point.x).is_some())
-            .filter(|b| !beam_history.contains(b))
             .for_each(|b| beams.push_back(b));
     }
 
     beam_history
-        .into_iter()
-        .map(|b| b.point)
-        .collect::<std::collections::HashSet<Point>>()
-        .len()
+}
+
+fn simulate(data: &Data, starting_beam: &Beam) -> usize {
+    energize(data, starting_beam)
+        .iter()
+        .filter(|p| p.value != 0)
+        .count()
+}
+
+// Draw the contraption with every energized tile replaced by '#'.
+fn render(data: &Data, energized: &Grid2D<u8>) -> String {
+    (0..data.0.get_height())
+        .map(|y| {
+            (0..data.0.get_width())
+                .map(|x| match energized.get_yx(y, x) {
+                    Some(0) => data.0.get_yx(y, x).unwrap().to_string(),
+                    _ => "#".to_string(),
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
 }
 
 fn part1(data: &Data) -> usize {
@@ -326,23 +262,374 @@ fn part1(data: &Data) -> usize {
     simulate(data, &starting_beam)
 }
 
-fn part2(data: &Data) -> usize {
+fn starting_beams(data: &Data) -> Vec<Beam> {
     let max_x = data.0.get_width() - 1;
     let max_y = data.0.get_height() - 1;
 
-    let starting_beams = (0..=max_x)
+    (0..=max_x)
         .map(|x| Beam::new(Point::new(x, 0), Direction::South))
         .chain((0..=max_y).map(|y| Beam::new(Point::new(max_x, y), Direction::West)))
         .chain((0..=max_x).map(|x| Beam::new(Point::new(x, max_y), Direction::North)))
         .chain((0..=max_y).map(|y| Beam::new(Point::new(0, y), Direction::East)))
-        .collect::<Vec<_>>();
+        .collect::<Vec<_>>()
+}
 
-    let result = starting_beams
-        .iter()
-        .map(|sb| (sb, simulate(data, sb)))
+fn n_threads() -> usize {
+    std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+}
+
+// Simulate every starting beam on a pool of threads which pull the next unclaimed index from a
+// shared counter.  Results are written back by index so they match the serial order exactly.
+fn simulate_all(data: &Data, starting_beams: &[Beam]) -> Vec<usize> {
+    let next = AtomicUsize::new(0);
+
+    let mut results = std::thread::scope(|s| {
+        (0..n_threads())
+            .map(|_| {
+                s.spawn(|| {
+                    let mut results = vec![];
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        match starting_beams.get(i) {
+                            Some(sb) => results.push((i, simulate(data, sb))),
+                            None => break results,
+                        }
+                    }
+                })
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .flat_map(|h| h.join().unwrap())
+            .collect::<Vec<_>>()
+    });
+    results.sort();
+
+    results.into_iter().map(|(_, v)| v).collect()
+}
+
+// Set of tile indices (y * width + x) packed into bits.
+#[derive(Debug, Clone)]
+struct TileSet(Vec<u64>);
+
+impl TileSet {
+    fn new(n: usize) -> Self {
+        Self(vec![0; n.div_ceil(64)])
+    }
+
+    fn insert(&mut self, i: usize) {
+        self.0[i / 64] |= 1 << (i % 64);
+    }
+
+    fn union(&mut self, other: &Self) {
+        self.0
+            .iter_mut()
+            .zip(other.0.iter())
+            .for_each(|(a, b)| *a |= b);
+    }
+
+    fn len(&self) -> usize {
+        self.0.iter().map(|v| v.count_ones() as usize).sum()
+    }
+}
+
+// The tiles (y * width + x) a single beam crosses until it is split by a splitter (the
+// splitter's node index) or leaves the contraption.  Segments are short compared to the grid so
+// the tiles are kept as a list rather than a TileSet.
+#[derive(Debug)]
+struct Segment {
+    tiles: Vec<usize>,
+    splitter: Option<usize>,
+}
+
+// Graph of splitter-to-splitter beam segments.  Every beam that gets split by a given splitter
+// continues identically from there, so each splitter is a node whose energized tiles only need
+// computing once.  Beams loop through splitters, so the strongly connected components are
+// collapsed and the energized tiles are accumulated per component.
+#[derive(Debug)]
+struct BeamGraph {
+    width: usize,
+    // node index of every splitter tile (y * width + x)
+    nodes: HashMap<usize, usize>,
+    // component of every node
+    component: Vec<usize>,
+    // tiles energized once a beam is split by any splitter of the component
+    energized: Vec<TileSet>,
+}
+
+impl BeamGraph {
+    fn new(data: &Data) -> Self {
+        let width = data.0.get_width();
+        let nodes = data
+            .0
+            .iter()
+            .filter(|p| matches!(p.value, Tile::Vertical | Tile::Horizontal))
+            .enumerate()
+            .map(|(n, p)| (p.y * width + p.x, n))
+            .collect::<HashMap<_, _>>();
+
+        let mut rv = Self {
+            width,
+            nodes,
+            component: vec![],
+            energized: vec![],
+        };
+
+        // trace both beams leaving every splitter
+        let mut tiles = vec![vec![]; rv.nodes.len()];
+        let mut succ = vec![vec![]; rv.nodes.len()];
+        for (i, n) in rv.nodes.iter() {
+            let point = Point::new(i % width, i / width);
+            let tile = data.0.get_yx(point.y, point.x).unwrap();
+            let splitting_dir = match tile {
+                Tile::Vertical => Direction::East,
+                _ => Direction::North,
+            };
+
+            tiles[*n].push(*i);
+            for beam in Beam::new(point, splitting_dir)
+                .next(&tile)
+                .filter(|b| data.0.get_yx(b.point.y, b.point.x).is_some())
+            {
+                let segment = rv.trace(data, &beam);
+                tiles[*n].extend(segment.tiles);
+                succ[*n].extend(segment.splitter);
+            }
+        }
+
+        // components are numbered in reverse topological order so every successor component is
+        // complete before it is merged into its predecessors; each node's tiles are dropped as
+        // soon as they are merged
+        let (component, n_components) = strongly_connected_components(&succ);
+        let mut energized = vec![TileSet::new(0); n_components];
+        let mut members = vec![vec![]; n_components];
+        for (n, c) in component.iter().enumerate() {
+            members[*c].push(n);
+        }
+
+        for (c, members) in members.iter().enumerate() {
+            let (done, rest) = energized.split_at_mut(c);
+            rest[0] = TileSet::new(data.0.get_width() * data.0.get_height());
+            for n in members.iter() {
+                std::mem::take(&mut tiles[*n])
+                    .into_iter()
+                    .for_each(|t| rest[0].insert(t));
+                succ[*n]
+                    .iter()
+                    .filter(|s| component[**s] != c)
+                    .for_each(|s| rest[0].union(&done[component[*s]]));
+            }
+        }
+
+        rv.component = component;
+        rv.energized = energized;
+
+        rv
+    }
+
+    // Follow a beam until it is split or leaves the contraption.  Outside of splitting, every
+    // tile maps each entering direction to a distinct exit, so the only loop possible is one
+    // that returns to the starting state.
+    fn trace(&self, data: &Data, starting_beam: &Beam) -> Segment {
+        let mut tiles = vec![];
+        let mut beam = starting_beam.clone();
+
+        loop {
+            let i = beam.point.y * self.width + beam.point.x;
+            tiles.push(i);
+
+            let tile = data.0.get_yx(beam.point.y, beam.point.x).unwrap();
+            if BEAM_EXITS[tile as usize][beam.dir as usize].len() > 1 {
+                return Segment {
+                    tiles,
+                    splitter: self.nodes.get(&i).copied(),
+                };
+            }
+
+            let next = beam
+                .next(&tile)
+                .find(|b| data.0.get_yx(b.point.y, b.point.x).is_some());
+            match next {
+                Some(next) if next != *starting_beam => beam = next,
+                _ => {
+                    return Segment {
+                        tiles,
+                        splitter: None,
+                    }
+                }
+            }
+        }
+    }
+
+    fn energized(&self, data: &Data, starting_beam: &Beam) -> usize {
+        let segment = self.trace(data, starting_beam);
+        let mut tiles = match segment.splitter {
+            Some(n) => self.energized[self.component[n]].clone(),
+            None => TileSet::new(data.0.get_width() * data.0.get_height()),
+        };
+        segment.tiles.into_iter().for_each(|t| tiles.insert(t));
+
+        tiles.len()
+    }
+}
+
+// Tarjan's algorithm (iterative), returning the component of every node and the number of
+// components.  Components are numbered in the order they complete, i.e. reverse topological.
+fn strongly_connected_components(succ: &[Vec<usize>]) -> (Vec<usize>, usize) {
+    const UNVISITED: usize = usize::MAX;
+
+    let mut index = vec![UNVISITED; succ.len()];
+    let mut lowlink = vec![0; succ.len()];
+    let mut on_stack = vec![false; succ.len()];
+    let mut stack = vec![];
+    let mut component = vec![0; succ.len()];
+    let mut n_components = 0;
+    let mut next_index = 0;
+
+    for root in 0..succ.len() {
+        if index[root] != UNVISITED {
+            continue;
+        }
+
+        // (node, position of the next successor to visit)
+        let mut calls = vec![(root, 0)];
+        index[root] = next_index;
+        lowlink[root] = next_index;
+        next_index += 1;
+        stack.push(root);
+        on_stack[root] = true;
+
+        while let Some((v, i)) = calls.pop() {
+            if let Some(&w) = succ[v].get(i) {
+                calls.push((v, i + 1));
+                if index[w] == UNVISITED {
+                    index[w] = next_index;
+                    lowlink[w] = next_index;
+                    next_index += 1;
+                    stack.push(w);
+                    on_stack[w] = true;
+                    calls.push((w, 0));
+                } else if on_stack[w] {
+                    lowlink[v] = lowlink[v].min(index[w]);
+                }
+                continue;
+            }
+
+            if lowlink[v] == index[v] {
+                loop {
+                    let w = stack.pop().unwrap();
+                    on_stack[w] = false;
+                    component[w] = n_components;
+                    if w == v {
+                        break;
+                    }
+                }
+                n_components += 1;
+            }
+
+            if let Some(&(u, _)) = calls.last() {
+                lowlink[u] = lowlink[u].min(lowlink[v]);
+            }
+        }
+    }
+
+    (component, n_components)
+}
+
+fn part2(data: &Data) -> usize {
+    let graph = BeamGraph::new(data);
+
+    let result = starting_beams(data)
+        .into_iter()
+        .map(|sb| {
+            let n = graph.energized(data, &sb);
+            (sb, n)
+        })
         .max_by(|a, b| a.1.cmp(&(b.1)))
         .unwrap();
     // println!("part2: result: {result:?}");
 
     result.1
 }
+
+fn part2_parallel(data: &Data) -> usize {
+    let starting_beams = starting_beams(data);
+
+    simulate_all(data, &starting_beams)
+        .into_iter()
+        .max()
+        .unwrap()
+}
+
+// Table of the energized tile count for every starting beam, one line per edge, followed by a
+// render of the best one.
+fn heatmap(data: &Data) -> String {
+    let starting_beams = starting_beams(data);
+    let energized = simulate_all(data, &starting_beams);
+
+    let width = energized.iter().max().unwrap().to_string().len();
+    let edges = [
+        ("top", data.0.get_width()),
+        ("right", data.0.get_height()),
+        ("bottom", data.0.get_width()),
+        ("left", data.0.get_height()),
+    ];
+
+    let mut rv = vec![];
+    let mut offset = 0;
+    for (edge, len) in edges {
+        let counts = energized[offset..(offset + len)]
+            .iter()
+            .map(|v| format!("{v:>width$}"))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let label = format!("{edge} ({:?}):", starting_beams[offset].dir);
+        rv.push(format!("{label:<15} {counts}"));
+        offset += len;
+    }
+
+    let (best, n) = starting_beams
+        .iter()
+        .zip(energized.iter())
+        .max_by(|a, b| a.1.cmp(b.1))
+        .unwrap();
+    rv.push(format!("max: {n} from {best:?}"));
+    rv.push(render(data, &energize(data, best)));
+
+    rv.join("\n")
+}
+
+fn part2_serial(data: &Data) -> usize {
+    starting_beams(data)
+        .iter()
+        .map(|sb| simulate(data, sb))
+        .max()
+        .unwrap()
+}
+
+fn bench_part2(data: &Data) {
+    let start = Instant::now();
+    let serial = part2_serial(data);
+    let serial_elapsed = start.elapsed();
+
+    let start = Instant::now();
+    let parallel = part2_parallel(data);
+    let parallel_elapsed = start.elapsed();
+
+    let start = Instant::now();
+    let graph = part2(data);
+    let graph_elapsed = start.elapsed();
+
+    assert_eq!(serial, parallel);
+    assert_eq!(serial, graph);
+    println!(
+        "bench: part2: serial {serial_elapsed:?}, parallel {parallel_elapsed:?} ({} threads), speedup {:.2}x",
+        n_threads(),
+        serial_elapsed.as_secs_f64() / parallel_elapsed.as_secs_f64()
+    );
+    println!(
+        "bench: part2: serial {serial_elapsed:?}, graph {graph_elapsed:?}, speedup {:.2}x",
+        serial_elapsed.as_secs_f64() / graph_elapsed.as_secs_f64()
+    );
+}
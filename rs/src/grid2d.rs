@@ -40,6 +40,14 @@ impl<T: Copy> Grid2D<T> {
         Ok(rv)
     }
 
+    pub fn filled(width: usize, height: usize, v: T) -> Self {
+        Self {
+            grid: vec![v; width * height],
+            width,
+            height,
+        }
+    }
+
     pub fn get_width(&self) -> usize {
         self.width
     }
@@ -52,7 +60,7 @@ impl<T: Copy> Grid2D<T> {
         self.yx_to_index(y, x).map(|i| self.grid[i])
     }
 
-    fn set_yx(&mut self, y: usize, x: usize, v: T) -> Option<usize> {
+    pub fn set_yx(&mut self, y: usize, x: usize, v: T) -> Option<usize> {
         match self.yx_to_index(y, x) {
             Some(i) => {
                 self.grid[i] = v;
@@ -78,7 +86,7 @@ impl<T: Copy> Grid2D<T> {
         }
     }
 
-    pub fn iter(&self) -> Grid2DIter<T> {
+    pub fn iter(&self) -> Grid2DIter<'_, T> {
         Grid2DIter::<T> {
             grid: self,
             index: 0,
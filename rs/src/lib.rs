@@ -11,3 +11,13 @@ pub fn read_stdin() -> Result<String, std::io::Error> {
 
     Ok(input)
 }
+
+pub fn has_flag(name: &str) -> bool {
+    std::env::args().skip(1).any(|a| a == name)
+}
+
+pub fn flag_value(name: &str) -> Option<String> {
+    let mut args = std::env::args().skip(1);
+    args.find(|a| a == name)?;
+    args.next()
+}